@@ -23,7 +23,7 @@ use crate::{
     mailer::EmailSender,
     storage::Storage,
     task::Tasks,
-    worker::{Pool, Processor, RedisConnectionManager},
+    worker::{Pool, Processor, QueueMiddleware, RedisConnectionManager},
     Result,
 };
 
@@ -171,6 +171,12 @@ pub trait Hooks {
     /// [`Processor`] and [`AppContext`].
     fn connect_workers<'a>(p: &'a mut Processor, ctx: &'a AppContext);
 
+    /// Provides the [`QueueMiddleware`]s that wrap every job pulled from the
+    /// queue. They run in the order of the returned list.
+    async fn queue_middlewares(_ctx: &AppContext) -> Result<Vec<Arc<dyn QueueMiddleware>>> {
+        Ok(vec![])
+    }
+
     /// Registers custom tasks with the provided [`Tasks`] object.
     fn register_tasks(tasks: &mut Tasks);
 
@@ -1,6 +1,8 @@
 //! # Application Bootstrapping and Logic
 //! This module contains functions and structures for bootstrapping and running
 //! your application.
+use std::sync::Arc;
+
 use axum::Router;
 #[cfg(feature = "with-db")]
 use sea_orm_migration::MigratorTrait;
@@ -272,7 +274,7 @@ pub async fn run_app<H: Hooks>(mode: &StartMode, app_context: AppContext) -> Res
             })
         }
         StartMode::ServerAndWorker => {
-            let processor = create_processor::<H>(&app_context)?;
+            let processor = create_processor::<H>(&app_context).await?;
             let app = H::routes(&app_context).to_router(app_context.clone())?;
             let mut router = H::after_routes(app, &app_context).await?;
             for initializer in &initializers {
@@ -285,7 +287,7 @@ pub async fn run_app<H: Hooks>(mode: &StartMode, app_context: AppContext) -> Res
             })
         }
         StartMode::WorkerOnly => {
-            let processor = create_processor::<H>(&app_context)?;
+            let processor = create_processor::<H>(&app_context).await?;
             Ok(BootResult {
                 app_context,
                 router: None,
@@ -295,7 +297,7 @@ pub async fn run_app<H: Hooks>(mode: &StartMode, app_context: AppContext) -> Res
     }
 }
 /// Creates and configures a [`Processor`] for handling worker tasks.
-fn create_processor<H: Hooks>(app_context: &AppContext) -> Result<Processor> {
+async fn create_processor<H: Hooks>(app_context: &AppContext) -> Result<Processor> {
     let queues = worker::get_queues(&app_context.config.workers.queues);
     trace!(
         queues = ?queues,
@@ -315,6 +317,15 @@ fn create_processor<H: Hooks>(app_context: &AppContext) -> Result<Processor> {
         ));
     };
 
+    p.using(worker::JobSpanMiddleware).await;
+    let mut middlewares: Vec<Arc<dyn worker::QueueMiddleware>> = vec![Arc::new(
+        worker::JobConcurrencyMiddleware::new(&app_context.config.workers),
+    )];
+    middlewares.extend(H::queue_middlewares(app_context).await?);
+    middlewares.push(Arc::new(worker::JobTimeoutMiddleware(
+        app_context.config.workers.clone(),
+    )));
+    p.using(worker::QueueMiddlewareStack(middlewares)).await;
     p.register(MailerWorker::build(app_context));
     H::connect_workers(&mut p, app_context);
 
@@ -25,6 +25,7 @@ Notes:
 use std::{
     collections::BTreeMap,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use fs_err as fs;
@@ -36,6 +37,7 @@ use tracing::info;
 use crate::{environment::Environment, logger, Error, Result};
 
 const DEFAULT_SERVER_BINDING: &str = "[::]";
+pub(crate) const DEFAULT_QUEUE_PING_TIMEOUT: u64 = 1000;
 
 lazy_static! {
     static ref DEFAULT_FOLDER: PathBuf = PathBuf::from("config");
@@ -187,6 +189,7 @@ pub struct Database {
 /// redis:
 ///   uri: redis://127.0.0.1/
 ///   dangerously_flush: false
+///   ping_timeout: 1000
 /// ```
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Redis {
@@ -196,6 +199,14 @@ pub struct Redis {
     #[serde(default)]
     /// Flush redis when application loaded. Useful for `test`.
     pub dangerously_flush: bool,
+    /// Maximum time in milliseconds the `/_health` endpoint waits for the
+    /// queue ping before reporting the queue as unhealthy.
+    #[serde(default = "default_ping_timeout")]
+    pub ping_timeout: u64,
+}
+
+fn default_ping_timeout() -> u64 {
+    DEFAULT_QUEUE_PING_TIMEOUT
 }
 
 /// User authentication configuration.
@@ -298,6 +309,11 @@ impl Server {
 /// # config/development.yaml
 /// workers:
 ///   mode: BackgroundQueue
+///   job_timeout: 60000
+///   job_timeouts:
+///     DownloadWorker: 30000
+///   job_concurrency:
+///     DownloadWorker: 2
 /// ```
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct Workers {
@@ -306,6 +322,31 @@ pub struct Workers {
     /// Custom queue names declaration. Required if you set up a dedicated
     /// worker against a dedicated queue.
     pub queues: Option<Vec<String>>,
+    /// Timeouts in milliseconds, keyed by worker class name. A job that runs
+    /// longer than its worker's timeout is failed with a timeout error.
+    #[serde(default)]
+    pub job_timeouts: BTreeMap<String, u64>,
+    /// Timeout in milliseconds for worker classes missing from
+    /// `job_timeouts`.
+    pub job_timeout: Option<u64>,
+    /// Maximum number of jobs of a worker class that run at the same time in
+    /// this process, keyed by worker class name. Only applies to jobs pulled
+    /// from the queue.
+    #[serde(default)]
+    pub job_concurrency: BTreeMap<String, u32>,
+}
+
+impl Workers {
+    /// The timeout configured for the given worker class, falling back to
+    /// `job_timeout`.
+    #[must_use]
+    pub fn job_timeout_for(&self, class: &str) -> Option<Duration> {
+        self.job_timeouts
+            .get(class)
+            .copied()
+            .or(self.job_timeout)
+            .map(Duration::from_millis)
+    }
 }
 
 /// Worker mode configuration
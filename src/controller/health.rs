@@ -2,16 +2,29 @@
 //! reporting. These routes are commonly used to monitor the health of the
 //! application and its dependencies.
 
+use std::time::Duration;
+
 use axum::{extract::State, response::Response, routing::get};
 use serde::Serialize;
 
 use super::{format, routes::Routes};
-use crate::{app::AppContext, redis, Result};
+use crate::{app::AppContext, config, redis, Result};
 
 /// Represents the health status of the application.
 #[derive(Serialize)]
 struct Health {
     pub ok: bool,
+    pub queue: QueueHealth,
+}
+
+/// Represents the connectivity status of the worker queue backend.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum QueueHealth {
+    Ok,
+    Error,
+    /// No queue connection is configured for the application.
+    Disabled,
 }
 
 /// Check the healthiness of the application bt ping to the redis and the DB to
@@ -24,16 +37,85 @@ async fn health(State(ctx): State<AppContext>) -> Result<Response> {
             false
         }
     };
-    if let Some(pool) = ctx.queue {
-        if let Err(error) = redis::ping(&pool).await {
-            tracing::error!(err.msg = %error, err.detail = ?error, "health_redis_ping_error");
-            is_ok = false;
+    let queue = if let Some(pool) = ctx.queue {
+        let ping_timeout = ctx
+            .config
+            .queue
+            .as_ref()
+            .map_or(config::DEFAULT_QUEUE_PING_TIMEOUT, |queue| {
+                queue.ping_timeout
+            });
+        match tokio::time::timeout(Duration::from_millis(ping_timeout), redis::ping(&pool)).await {
+            Ok(Ok(())) => QueueHealth::Ok,
+            Ok(Err(error)) => {
+                tracing::error!(err.msg = %error, err.detail = ?error, "health_redis_ping_error");
+                is_ok = false;
+                QueueHealth::Error
+            }
+            Err(_) => {
+                tracing::error!(timeout_ms = ping_timeout, "health_redis_ping_timeout");
+                is_ok = false;
+                QueueHealth::Error
+            }
         }
-    }
-    format::json(Health { ok: is_ok })
+    } else {
+        QueueHealth::Disabled
+    };
+    format::json(Health { ok: is_ok, queue })
 }
 
 /// Defines and returns the health-related routes.
 pub fn routes() -> Routes {
     Routes::new().add("/_health", get(health))
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::get, Router};
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        tests_cfg,
+        worker::{Pool, RedisConnectionManager},
+    };
+
+    async fn request_health(ctx: AppContext) -> axum_test::TestResponse {
+        let router = Router::new().route("/_health", get(health)).with_state(ctx);
+        TestServer::new(router).unwrap().get("/_health").await
+    }
+
+    #[tokio::test]
+    async fn reports_disabled_queue() {
+        let ctx = tests_cfg::app::get_app_context().await;
+
+        let response = request_health(ctx).await;
+
+        response.assert_status_ok();
+        response.assert_json(&json!({ "ok": true, "queue": "disabled" }));
+    }
+
+    #[tokio::test]
+    async fn reports_unreachable_queue_within_ping_timeout() {
+        let mut ctx = tests_cfg::app::get_app_context().await;
+        ctx.config
+            .queue
+            .as_mut()
+            .expect("demo test config declares a queue")
+            .ping_timeout = 200;
+        // nothing listens on port 1, so acquiring a connection keeps retrying
+        // until the pool's own (much longer) connection timeout
+        let manager = RedisConnectionManager::new("redis://127.0.0.1:1").unwrap();
+        ctx.queue = Some(Pool::builder().build_unchecked(manager));
+
+        // stays below the default ping timeout, so this fails if the configured
+        // value is ignored
+        let response = tokio::time::timeout(Duration::from_millis(800), request_health(ctx))
+            .await
+            .expect("health endpoint should answer within the configured ping timeout");
+
+        response.assert_status_ok();
+        response.assert_json(&json!({ "ok": false, "queue": "error" }));
+    }
+}
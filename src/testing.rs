@@ -13,6 +13,7 @@ use crate::{
     app::{AppContext, Hooks},
     boot::{self, BootResult},
     environment::Environment,
+    worker::{AppWorker, Worker},
     Result,
 };
 
@@ -202,3 +203,225 @@ where
 
     callback(server, boot.app_context.clone()).await;
 }
+
+/// Runs a worker's `perform` directly in the current task, bypassing the
+/// queue entirely. This lets tests exercise worker logic without a Redis
+/// connection, regardless of the configured worker mode.
+///
+/// The worker is passed in already built, so tests that don't need an
+/// [`AppContext`] can construct it however they like. See
+/// [`run_app_worker_in_process`] to build it from the app context instead.
+///
+/// # Panics
+/// When the worker returns an error.
+///
+/// # Example
+///
+/// The provided example demonstrates how to run a worker in a test and then
+/// assert on its side effects.
+///
+/// ```rust,ignore
+/// use myapp::workers::downloader::{DownloadWorker, DownloadWorkerArgs};
+/// use loco_rs::testing;
+///
+/// #[tokio::test]
+/// async fn can_download() {
+///     let worker = DownloadWorker::new(/* .. */);
+///     testing::run_worker_in_process(
+///         worker,
+///         DownloadWorkerArgs { user_guid: "foo".to_string() },
+///     )
+///     .await;
+///
+///     /// .....
+/// }
+/// ```
+pub async fn run_worker_in_process<W, T>(worker: W, args: T)
+where
+    W: Worker<T>,
+    T: Send + Sync + 'static,
+{
+    if let Err(err) = worker.perform(args).await {
+        panic!("worker `{}` failed: {err}", std::any::type_name::<W>());
+    }
+}
+
+/// Builds a worker from the app context with [`AppWorker::build`] and runs it
+/// with [`run_worker_in_process`].
+///
+/// # Panics
+/// When the worker returns an error.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use myapp::app::App;
+/// use myapp::workers::downloader::{DownloadWorker, DownloadWorkerArgs};
+/// use loco_rs::testing;
+///
+/// #[tokio::test]
+/// async fn can_download() {
+///     let boot = testing::boot_test::<App>().await.unwrap();
+///     testing::run_app_worker_in_process::<DownloadWorker, _>(
+///         &boot.app_context,
+///         DownloadWorkerArgs { user_guid: "foo".to_string() },
+///     )
+///     .await;
+///
+///     /// .....
+/// }
+/// ```
+pub async fn run_app_worker_in_process<W, T>(ctx: &AppContext, args: T)
+where
+    W: AppWorker<T>,
+    T: Send + Sync + serde::Serialize + 'static,
+{
+    run_worker_in_process(W::build(ctx), args).await;
+}
+
+/// Serializes worker args into the JSON `args` value a queued job carries.
+///
+/// This follows what sidekiq's `perform_async` does when a job is pushed: the
+/// args are converted with `serde_json` and any value that is not already an
+/// array is wrapped into a one-element array.
+///
+/// # Errors
+/// When the args could not be serialized
+pub fn serialize_args<T: serde::Serialize>(args: &T) -> Result<serde_json::Value> {
+    let value = serde_json::to_value(args)?;
+    Ok(if value.is_array() {
+        value
+    } else {
+        serde_json::Value::Array(vec![value])
+    })
+}
+
+/// Deserializes worker args from the JSON `args` value of a queued job, the
+/// way the sidekiq worker wrapper does before calling `perform`: a
+/// one-element array is unwrapped first, and `()` args ignore the value.
+///
+/// Together with [`serialize_args`], this catches args types that break on
+/// their way through the queue. For example a `Vec<String>` holding a single
+/// item is stored as-is and then unwrapped to a plain string, which fails to
+/// deserialize.
+///
+/// # Errors
+/// When the value could not be deserialized into `T`
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use myapp::workers::downloader::DownloadWorkerArgs;
+/// use loco_rs::testing;
+///
+/// #[test]
+/// fn download_args_round_trip() {
+///     let args = DownloadWorkerArgs { user_guid: "foo".to_string() };
+///     let value = testing::serialize_args(&args).unwrap();
+///     let back: DownloadWorkerArgs = testing::deserialize_args(value).unwrap();
+///     assert_eq!(back.user_guid, args.user_guid);
+/// }
+/// ```
+pub fn deserialize_args<T>(value: serde_json::Value) -> Result<T>
+where
+    T: serde::de::DeserializeOwned + 'static,
+{
+    if std::any::TypeId::of::<T>() == std::any::TypeId::of::<()>() {
+        return Ok(serde_json::from_value(serde_json::Value::Null)?);
+    }
+    let value = match value {
+        serde_json::Value::Array(mut items) if items.len() == 1 => items.remove(0),
+        value => value,
+    };
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::mailer::Email;
+
+    #[test]
+    fn can_round_trip_worker_args() {
+        let email = Email {
+            to: "user@example.com".to_string(),
+            subject: "welcome".to_string(),
+            cc: Some("cc@example.com".to_string()),
+            ..Default::default()
+        };
+
+        let value = serialize_args(&email).unwrap();
+        assert_eq!(value[0]["to"], json!("user@example.com"));
+        assert_eq!(value[0]["from"], json!(null));
+
+        let back: Email = deserialize_args(value.clone()).unwrap();
+        assert_eq!(serialize_args(&back).unwrap(), value);
+    }
+
+    #[test]
+    fn single_item_vec_args_do_not_survive_the_queue() {
+        let value = serialize_args(&vec!["only".to_string()]).unwrap();
+        assert_eq!(value, json!(["only"]));
+
+        assert!(deserialize_args::<Vec<String>>(value).is_err());
+        assert_eq!(
+            deserialize_args::<Vec<String>>(serialize_args(&vec!["a", "b"]).unwrap()).unwrap(),
+            vec!["a".to_string(), "b".to_string()]
+        );
+    }
+
+    #[test]
+    fn can_round_trip_tuple_args() {
+        let value = serialize_args(&("report".to_string(), 42)).unwrap();
+        assert_eq!(value, json!(["report", 42]));
+
+        let back: (String, i32) = deserialize_args(value).unwrap();
+        assert_eq!(back, ("report".to_string(), 42));
+    }
+
+    #[test]
+    fn can_round_trip_unit_args() {
+        let value = serialize_args(&()).unwrap();
+        assert_eq!(value, json!([null]));
+
+        deserialize_args::<()>(value).unwrap();
+        deserialize_args::<()>(json!([])).unwrap();
+    }
+
+    #[test]
+    fn deserialize_args_rejects_mismatched_shape() {
+        assert!(deserialize_args::<Email>(json!([{ "to": 42 }])).is_err());
+    }
+
+    mod run_worker {
+        use crate::{
+            testing::{run_app_worker_in_process, run_worker_in_process},
+            tests_cfg::worker::{TestWorker, TestWorkerArgs},
+        };
+
+        #[tokio::test]
+        async fn runs_worker_perform() {
+            let worker = TestWorker::default();
+
+            run_worker_in_process(worker.clone(), TestWorkerArgs::Succeed).await;
+
+            assert_eq!(worker.performed(), 1);
+        }
+
+        #[tokio::test]
+        #[should_panic(expected = "TestWorker` failed")]
+        async fn panics_when_worker_fails() {
+            run_worker_in_process(TestWorker::default(), TestWorkerArgs::Fail).await;
+        }
+
+        #[cfg(feature = "with-db")]
+        #[tokio::test]
+        async fn builds_worker_from_app_context() {
+            let ctx = crate::tests_cfg::app::get_app_context().await;
+
+            run_app_worker_in_process::<TestWorker, _>(&ctx, TestWorkerArgs::Succeed).await;
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    app::AppContext,
+    cache,
+    environment::Environment,
+    storage::{self, Storage},
+};
+
+/// Creating an [`AppContext`] for tests, loading the demo test configuration
+/// and connecting to an in-memory sqlite database. No queue is attached.
+///
+/// # Panics
+/// When the demo configuration could not be loaded or the database could not
+/// be connected
+pub async fn get_app_context() -> AppContext {
+    let config = Environment::Test
+        .load_from_folder(Path::new("examples/demo/config"))
+        .expect("load demo test configuration");
+
+    AppContext {
+        environment: Environment::Test,
+        db: sea_orm::Database::connect("sqlite::memory:")
+            .await
+            .expect("connect in-memory sqlite"),
+        queue: None,
+        config,
+        mailer: None,
+        storage: Arc::new(Storage::single(storage::drivers::null::new())),
+        cache: Arc::new(cache::Cache::new(cache::drivers::null::new())),
+    }
+}
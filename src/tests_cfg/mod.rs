@@ -1,2 +1,5 @@
 #[cfg(feature = "with-db")]
+pub mod app;
+#[cfg(feature = "with-db")]
 pub mod db;
+pub mod worker;
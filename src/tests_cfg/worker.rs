@@ -0,0 +1,76 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    app::AppContext,
+    worker::{AppWorker, Job, Result, Worker},
+};
+
+/// What a [`TestWorker`] does when performed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TestWorkerArgs {
+    Succeed,
+    Fail,
+    Panic,
+    Hang,
+}
+
+/// A worker for tests: counts successful runs, and fails, panics or never
+/// finishes on request.
+#[derive(Debug, Clone, Default)]
+pub struct TestWorker {
+    performed: Arc<AtomicUsize>,
+}
+
+impl TestWorker {
+    /// Number of successful `perform` calls, shared between clones.
+    #[must_use]
+    pub fn performed(&self) -> usize {
+        self.performed.load(Ordering::SeqCst)
+    }
+}
+
+impl AppWorker<TestWorkerArgs> for TestWorker {
+    fn build(_ctx: &AppContext) -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Worker<TestWorkerArgs> for TestWorker {
+    async fn perform(&self, args: TestWorkerArgs) -> Result<()> {
+        match args {
+            TestWorkerArgs::Succeed => {
+                self.performed.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+            TestWorkerArgs::Fail => Err(sidekiq::Error::Message("boom".to_string())),
+            TestWorkerArgs::Panic => panic!("worker exploded"),
+            TestWorkerArgs::Hang => std::future::pending().await,
+        }
+    }
+}
+
+/// Builds a queue [`Job`] for the given worker class, as it would be pulled
+/// from Redis.
+///
+/// # Panics
+/// When the job payload doesn't match sidekiq's job format.
+#[must_use]
+pub fn job(class: &str) -> Job {
+    serde_json::from_value(json!({
+        "queue": "default",
+        "args": [],
+        "retry": true,
+        "class": class,
+        "jid": "test-jid",
+        "created_at": 0.0,
+    }))
+    .expect("test job")
+}
@@ -1,9 +1,20 @@
+use std::{
+    any::Any, collections::BTreeMap, future::Future, panic::AssertUnwindSafe, pin::Pin, sync::Arc,
+    time::Duration,
+};
+
 use async_trait::async_trait;
 pub use bb8::Pool;
-pub use sidekiq::{Processor, RedisConnectionManager, Result, Worker};
-use tracing::error;
+use futures_util::FutureExt;
+use sidekiq::{ChainIter, RedisPool, ServerMiddleware, WorkerRef};
+pub use sidekiq::{Job, Processor, RedisConnectionManager, Result, Worker};
+use tokio::sync::Semaphore;
+use tracing::{error, field, Instrument, Span};
 
-use super::{app::AppContext, config::WorkerMode};
+use super::{
+    app::AppContext,
+    config::{self, WorkerMode},
+};
 pub const DEFAULT_QUEUES: &[&str] = &["default", "mailer"];
 
 pub fn get_queues(config_queues: &Option<Vec<String>>) -> Vec<String> {
@@ -24,6 +35,218 @@ pub fn get_queues(config_queues: &Option<Vec<String>>) -> Vec<String> {
     queues
 }
 
+/// Creates the `job.execute` span that wraps a single worker execution. Queue
+/// related fields are left empty for executions that don't go through the
+/// queue.
+fn job_span(class: &str) -> Span {
+    tracing::info_span!(
+        "job.execute",
+        job.class = class,
+        job.id = field::Empty,
+        job.queue = field::Empty,
+        job.retry_count = field::Empty,
+        otel.status_code = field::Empty,
+        error = field::Empty,
+        exception.message = field::Empty,
+    )
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("worker panicked")
+}
+
+/// Runs a worker execution inside the given span and records its outcome on
+/// the span. A panic is recorded as an error, with its message in
+/// `exception.message`, and then resumed.
+async fn perform_in_span<F>(span: Span, perform: F) -> Result<()>
+where
+    F: Future<Output = Result<()>> + Send,
+{
+    match AssertUnwindSafe(perform)
+        .catch_unwind()
+        .instrument(span.clone())
+        .await
+    {
+        Ok(result) => {
+            match &result {
+                Ok(()) => {
+                    span.record("otel.status_code", "OK");
+                }
+                Err(err) => {
+                    span.record("otel.status_code", "ERROR");
+                    span.record("error", field::display(err));
+                }
+            }
+            result
+        }
+        Err(panic) => {
+            let message = panic_message(panic.as_ref());
+            span.record("otel.status_code", "ERROR");
+            span.record("error", message);
+            span.record("exception.message", message);
+            std::panic::resume_unwind(panic)
+        }
+    }
+}
+
+/// Fails `perform` with a "job timed out" error when it runs longer than
+/// `timeout`.
+async fn perform_with_timeout<F>(timeout: Option<Duration>, perform: F) -> Result<()>
+where
+    F: Future<Output = Result<()>> + Send,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, perform)
+            .await
+            .unwrap_or_else(|_| {
+                Err(sidekiq::Error::Message(format!(
+                    "job timed out after {timeout:?}"
+                )))
+            }),
+        None => perform.await,
+    }
+}
+
+/// Sidekiq server middleware that runs every job pulled from the queue inside
+/// a `job.execute` span carrying the job id, class, queue and retry count.
+/// Registered on the [`Processor`] when the application boots with workers.
+pub struct JobSpanMiddleware;
+
+#[async_trait]
+impl ServerMiddleware for JobSpanMiddleware {
+    async fn call(
+        &self,
+        chain: ChainIter,
+        job: &Job,
+        worker: Arc<WorkerRef>,
+        redis: RedisPool,
+    ) -> Result<()> {
+        let span = job_span(&job.class);
+        span.record("job.id", job.jid.as_str());
+        span.record("job.queue", job.queue.as_str());
+        span.record("job.retry_count", job.retry_count.unwrap_or(0));
+        perform_in_span(span, chain.next(job, worker, redis)).await
+    }
+}
+
+/// Wraps the execution of every job the queue worker runs, for cross-cutting
+/// concerns such as logging, rate limiting or acquiring a permit.
+///
+/// Middlewares are provided by [`crate::app::Hooks::queue_middlewares`] and
+/// run in the order they were registered, inside the `job.execute` span. Each
+/// one decides whether and when to continue the chain by calling
+/// [`Next::run`]. They apply to jobs pulled from the queue
+/// ([`WorkerMode::BackgroundQueue`]), not to the in-process worker modes.
+#[async_trait]
+pub trait QueueMiddleware: Send + Sync + 'static {
+    async fn call(&self, job: &Job, next: Next<'_>) -> Result<()>;
+}
+
+/// The rest of the middleware chain, ending with the worker's `perform`.
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn QueueMiddleware>],
+    perform: Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        middlewares: &'a [Arc<dyn QueueMiddleware>],
+        perform: impl Future<Output = Result<()>> + Send + 'a,
+    ) -> Self {
+        Self {
+            middlewares,
+            perform: Box::pin(perform),
+        }
+    }
+
+    /// Runs the next middleware, or the job itself once every middleware
+    /// has been called.
+    ///
+    /// # Errors
+    /// When a middleware or the job returns an error.
+    pub async fn run(self, job: &Job) -> Result<()> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .call(
+                        job,
+                        Next {
+                            middlewares: rest,
+                            perform: self.perform,
+                        },
+                    )
+                    .await
+            }
+            None => self.perform.await,
+        }
+    }
+}
+
+/// Sidekiq server middleware that runs the registered [`QueueMiddleware`]s
+/// around each job, in order.
+pub(crate) struct QueueMiddlewareStack(pub(crate) Vec<Arc<dyn QueueMiddleware>>);
+
+#[async_trait]
+impl ServerMiddleware for QueueMiddlewareStack {
+    async fn call(
+        &self,
+        chain: ChainIter,
+        job: &Job,
+        worker: Arc<WorkerRef>,
+        redis: RedisPool,
+    ) -> Result<()> {
+        let perform = async move { chain.next(job, worker, redis).await };
+        Next::new(&self.0, perform).run(job).await
+    }
+}
+
+/// Applies the configured job timeouts to jobs pulled from the queue.
+pub(crate) struct JobTimeoutMiddleware(pub(crate) config::Workers);
+
+#[async_trait]
+impl QueueMiddleware for JobTimeoutMiddleware {
+    async fn call(&self, job: &Job, next: Next<'_>) -> Result<()> {
+        perform_with_timeout(self.0.job_timeout_for(&job.class), next.run(job)).await
+    }
+}
+
+/// Limits how many jobs of a worker class run at the same time, using the
+/// configured `job_concurrency`. A job over the limit waits for a running job
+/// of its class to finish.
+pub(crate) struct JobConcurrencyMiddleware(BTreeMap<String, Arc<Semaphore>>);
+
+impl JobConcurrencyMiddleware {
+    pub(crate) fn new(config: &config::Workers) -> Self {
+        Self(
+            config
+                .job_concurrency
+                .iter()
+                .map(|(class, limit)| (class.clone(), Arc::new(Semaphore::new(*limit as usize))))
+                .collect(),
+        )
+    }
+}
+
+#[async_trait]
+impl QueueMiddleware for JobConcurrencyMiddleware {
+    async fn call(&self, job: &Job, next: Next<'_>) -> Result<()> {
+        let _permit = match self.0.get(&job.class) {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .map_err(|err| sidekiq::Error::Message(err.to_string()))?,
+            ),
+            None => None,
+        };
+        next.run(job).await
+    }
+}
+
 #[async_trait]
 #[allow(clippy::module_name_repetitions)]
 pub trait AppWorker<T>: Worker<T>
@@ -46,13 +269,299 @@ where
                 }
             }
             WorkerMode::ForegroundBlocking => {
-                Self::build(ctx).perform(args).await.unwrap();
+                let timeout = ctx.config.workers.job_timeout_for(&Self::class_name());
+                perform_in_span(
+                    job_span(&Self::class_name()),
+                    perform_with_timeout(timeout, Self::build(ctx).perform(args)),
+                )
+                .await
+                .unwrap();
             }
             WorkerMode::BackgroundAsync => {
                 let dx = ctx.clone();
-                tokio::spawn(async move { Self::build(&dx).perform(args).await });
+                let timeout = ctx.config.workers.job_timeout_for(&Self::class_name());
+                tokio::spawn(perform_in_span(job_span(&Self::class_name()), async move {
+                    perform_with_timeout(timeout, Self::build(&dx).perform(args)).await
+                }));
             }
         }
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "with-db"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    use super::*;
+    use crate::{
+        tests_cfg,
+        tests_cfg::worker::{self as test_worker, TestWorker, TestWorkerArgs},
+    };
+
+    type SpanFields = Arc<Mutex<HashMap<String, String>>>;
+
+    /// Collects the fields recorded on `job.execute` spans.
+    struct CaptureJobSpan(SpanFields);
+
+    impl Visit for CaptureJobSpan {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for CaptureJobSpan {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            if attrs.metadata().name() == "job.execute" {
+                attrs.record(&mut Self(self.0.clone()));
+            }
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            if ctx
+                .span(id)
+                .is_some_and(|span| span.name() == "job.execute")
+            {
+                values.record(&mut Self(self.0.clone()));
+            }
+        }
+    }
+
+    async fn perform_foreground(args: TestWorkerArgs) -> (bool, HashMap<String, String>) {
+        perform_foreground_with(config::Workers::default(), args).await
+    }
+
+    async fn perform_foreground_with(
+        workers: config::Workers,
+        args: TestWorkerArgs,
+    ) -> (bool, HashMap<String, String>) {
+        let fields = SpanFields::default();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(CaptureJobSpan(fields.clone())),
+        );
+        let mut ctx = tests_cfg::app::get_app_context().await;
+        ctx.config.workers = workers;
+        ctx.config.workers.mode = WorkerMode::ForegroundBlocking;
+
+        let completed = AssertUnwindSafe(TestWorker::perform_later(&ctx, args))
+            .catch_unwind()
+            .await
+            .is_ok();
+
+        let fields = fields.lock().unwrap().clone();
+        (completed, fields)
+    }
+
+    #[tokio::test]
+    async fn job_span_records_success() {
+        let (completed, fields) = perform_foreground(TestWorkerArgs::Succeed).await;
+
+        assert!(completed);
+        assert_eq!(fields.get("job.class"), Some(&TestWorker::class_name()));
+        assert_eq!(
+            fields.get("otel.status_code").map(String::as_str),
+            Some("OK")
+        );
+        assert!(!fields.contains_key("error"));
+    }
+
+    #[tokio::test]
+    async fn job_span_records_error_before_foreground_unwrap() {
+        let (completed, fields) = perform_foreground(TestWorkerArgs::Fail).await;
+
+        assert!(!completed);
+        assert_eq!(
+            fields.get("otel.status_code").map(String::as_str),
+            Some("ERROR")
+        );
+        assert!(fields.get("error").is_some_and(|err| err.contains("boom")));
+        assert!(!fields.contains_key("exception.message"));
+    }
+
+    #[tokio::test]
+    async fn job_span_records_worker_panic() {
+        let (completed, fields) = perform_foreground(TestWorkerArgs::Panic).await;
+
+        assert!(!completed);
+        assert_eq!(
+            fields.get("otel.status_code").map(String::as_str),
+            Some("ERROR")
+        );
+        assert_eq!(
+            fields.get("error").map(String::as_str),
+            Some("worker exploded")
+        );
+        assert_eq!(
+            fields.get("exception.message").map(String::as_str),
+            Some("worker exploded")
+        );
+    }
+
+    struct RecordOrder {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl QueueMiddleware for RecordOrder {
+        async fn call(&self, job: &Job, next: Next<'_>) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} before", self.name));
+            let res = next.run(job).await;
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} after", self.name));
+            res
+        }
+    }
+
+    #[tokio::test]
+    async fn queue_middlewares_run_in_registration_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let middlewares: Vec<Arc<dyn QueueMiddleware>> = ["first", "second"]
+            .into_iter()
+            .map(|name| {
+                Arc::new(RecordOrder {
+                    name,
+                    calls: calls.clone(),
+                }) as Arc<dyn QueueMiddleware>
+            })
+            .collect();
+        let job = test_worker::job(&TestWorker::class_name());
+
+        let perform_calls = calls.clone();
+        Next::new(&middlewares, async move {
+            perform_calls.lock().unwrap().push("perform".to_string());
+            Ok(())
+        })
+        .run(&job)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "first before",
+                "second before",
+                "perform",
+                "second after",
+                "first after"
+            ]
+        );
+    }
+
+    fn timeouts(class: &str, millis: u64) -> config::Workers {
+        config::Workers {
+            job_timeouts: [(class.to_string(), millis)].into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn foreground_job_fails_after_class_timeout() {
+        let workers = timeouts(&TestWorker::class_name(), 10);
+
+        let (completed, fields) = perform_foreground_with(workers, TestWorkerArgs::Hang).await;
+
+        assert!(!completed);
+        assert!(fields
+            .get("error")
+            .is_some_and(|err| err.contains("job timed out after 10ms")));
+    }
+
+    #[tokio::test]
+    async fn queue_job_fails_after_class_timeout() {
+        let middlewares: Vec<Arc<dyn QueueMiddleware>> = vec![Arc::new(JobTimeoutMiddleware(
+            timeouts(&TestWorker::class_name(), 10),
+        ))];
+
+        let timed_out = Next::new(&middlewares, std::future::pending::<Result<()>>())
+            .run(&test_worker::job(&TestWorker::class_name()))
+            .await;
+        let other_class = Next::new(&middlewares, async { Ok(()) })
+            .run(&test_worker::job("OtherWorker"))
+            .await;
+
+        assert!(timed_out
+            .unwrap_err()
+            .to_string()
+            .contains("job timed out after 10ms"));
+        assert!(other_class.is_ok());
+    }
+
+    #[tokio::test]
+    async fn job_timeout_applies_to_classes_without_their_own() {
+        let workers = config::Workers {
+            job_timeout: Some(10),
+            ..timeouts("SlowWorker", 60_000)
+        };
+
+        assert_eq!(
+            workers.job_timeout_for("SlowWorker"),
+            Some(Duration::from_secs(60))
+        );
+        let (completed, fields) = perform_foreground_with(workers, TestWorkerArgs::Hang).await;
+
+        assert!(!completed);
+        assert!(fields
+            .get("error")
+            .is_some_and(|err| err.contains("job timed out after 10ms")));
+    }
+
+    #[tokio::test]
+    async fn job_concurrency_limits_jobs_of_a_class() {
+        let workers = config::Workers {
+            job_concurrency: [(TestWorker::class_name(), 2)].into_iter().collect(),
+            ..Default::default()
+        };
+        let middlewares: Vec<Arc<dyn QueueMiddleware>> =
+            vec![Arc::new(JobConcurrencyMiddleware::new(&workers))];
+        let job = test_worker::job(&TestWorker::class_name());
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let jobs = (0..5).map(|_| {
+            let running = running.clone();
+            let max_running = max_running.clone();
+            Next::new(&middlewares, async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            })
+            .run(&job)
+        });
+        for res in futures_util::future::join_all(jobs).await {
+            res.unwrap();
+        }
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    }
+}